@@ -0,0 +1,7 @@
+use yew_agent::Registrable;
+
+/// Entry point for the `DecodeWorker` web worker bundle, built as its own
+/// wasm binary and spawned by the main app from the `WORKER_JS` path.
+fn main() {
+    exodus::worker::DecodeWorker::registrar().register();
+}
@@ -0,0 +1,164 @@
+use anyhow::{anyhow, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Size in bytes of the Argon2 cost parameters (`m_cost`, `t_cost`, `p_cost`)
+/// stored at the front of the frame, each a little-endian `u32`.
+const PARAMS_LEN: usize = 12;
+
+/// Ceilings on the Argon2 cost parameters we're willing to honor out of an
+/// untrusted backup file. Comfortably above `Params::default()` so a future
+/// bump to the defaults still decrypts, but far below values that would make
+/// `hash_password_into` try to allocate gigabytes/run for minutes from a
+/// corrupted or maliciously crafted file.
+const MAX_M_COST: u32 = 256 * 1024; // 256 MiB, in KiB
+const MAX_T_COST: u32 = 50;
+const MAX_P_COST: u32 = 16;
+
+/// Encrypts `plaintext` with a key derived from `passphrase`.
+///
+/// The output is framed as `params || salt || nonce || ciphertext`, where
+/// `params` are the Argon2id cost parameters used to derive the key, `salt`
+/// is a fresh random salt and `nonce` is a fresh random XChaCha20-Poly1305
+/// nonce. Framing the parameters alongside the salt means a backup can
+/// always be decrypted from nothing but the passphrase, even after a future
+/// crate upgrade changes `Argon2::default()`'s parameters.
+pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let params = Params::default();
+
+    let mut salt = [0u8; SALT_LEN];
+    getrandom::getrandom(&mut salt)?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut nonce_bytes)?;
+
+    let key = derive_key(passphrase, &params, &salt)?;
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|_| anyhow!("failed to encrypt backup"))?;
+
+    let mut framed =
+        Vec::with_capacity(PARAMS_LEN + SALT_LEN + NONCE_LEN + ciphertext.len());
+    framed.extend_from_slice(&params.m_cost().to_le_bytes());
+    framed.extend_from_slice(&params.t_cost().to_le_bytes());
+    framed.extend_from_slice(&params.p_cost().to_le_bytes());
+    framed.extend_from_slice(&salt);
+    framed.extend_from_slice(&nonce_bytes);
+    framed.extend_from_slice(&ciphertext);
+
+    Ok(framed)
+}
+
+/// Reverses [`encrypt`], deriving the same key from `passphrase` and the
+/// parameters and salt stored in the frame.
+pub fn decrypt(passphrase: &str, framed: &[u8]) -> Result<Vec<u8>> {
+    if framed.len() < PARAMS_LEN + SALT_LEN + NONCE_LEN {
+        return Err(anyhow!("encrypted backup is truncated"));
+    }
+
+    let (params, rest) = framed.split_at(PARAMS_LEN);
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce_bytes, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let m_cost = u32::from_le_bytes(params[0..4].try_into().unwrap());
+    let t_cost = u32::from_le_bytes(params[4..8].try_into().unwrap());
+    let p_cost = u32::from_le_bytes(params[8..12].try_into().unwrap());
+
+    if m_cost > MAX_M_COST || t_cost > MAX_T_COST || p_cost > MAX_P_COST {
+        return Err(anyhow!(
+            "encrypted backup requests implausible Argon2 parameters"
+        ));
+    }
+
+    let params = Params::new(m_cost, t_cost, p_cost, None)
+        .map_err(|err| anyhow!("invalid backup key derivation parameters: {}", err))?;
+
+    let key = derive_key(passphrase, &params, salt)?;
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    let nonce = XNonce::from_slice(nonce_bytes);
+
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|_| anyhow!("wrong passphrase, or the backup is corrupted"))
+}
+
+fn derive_key(passphrase: &str, params: &Params, salt: &[u8]) -> Result<[u8; KEY_LEN]> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| anyhow!("failed to derive key from passphrase: {}", err))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decrypt_reverses_encrypt() {
+        let plaintext = b"some secret backup bytes";
+        let framed = encrypt("correct horse battery staple", plaintext).unwrap();
+
+        let decrypted = decrypt("correct horse battery staple", &framed).unwrap();
+
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn decrypt_rejects_implausible_m_cost() {
+        let mut framed = vec![0u8; PARAMS_LEN + SALT_LEN + NONCE_LEN];
+        framed[0..4].copy_from_slice(&(MAX_M_COST + 1).to_le_bytes());
+        framed[4..8].copy_from_slice(&1u32.to_le_bytes());
+        framed[8..12].copy_from_slice(&1u32.to_le_bytes());
+
+        let err = decrypt("anything", &framed).unwrap_err();
+        assert!(err.to_string().contains("implausible"));
+    }
+
+    #[test]
+    fn decrypt_rejects_wrong_passphrase() {
+        let framed = encrypt("correct horse battery staple", b"some secret backup bytes").unwrap();
+
+        assert!(decrypt("wrong passphrase", &framed).is_err());
+    }
+
+    #[test]
+    fn decrypt_is_pinned_to_the_framed_params_not_the_current_default() {
+        // Simulate a future crate upgrade changing Argon2::default()'s cost
+        // parameters: a backup encrypted with today's defaults must still
+        // decrypt even if `Params::default()` changes, because the frame
+        // carries its own params rather than relying on the decrypt side's
+        // current default.
+        let weaker_params = Params::new(8, 1, 1, None).unwrap();
+
+        let mut salt = [0u8; SALT_LEN];
+        getrandom::getrandom(&mut salt).unwrap();
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut nonce_bytes).unwrap();
+
+        let key = derive_key("hunter2", &weaker_params, &salt).unwrap();
+        let cipher = XChaCha20Poly1305::new((&key).into());
+        let nonce = XNonce::from_slice(&nonce_bytes);
+        let ciphertext = cipher.encrypt(nonce, &b"payload"[..]).unwrap();
+
+        let mut framed = Vec::new();
+        framed.extend_from_slice(&weaker_params.m_cost().to_le_bytes());
+        framed.extend_from_slice(&weaker_params.t_cost().to_le_bytes());
+        framed.extend_from_slice(&weaker_params.p_cost().to_le_bytes());
+        framed.extend_from_slice(&salt);
+        framed.extend_from_slice(&nonce_bytes);
+        framed.extend_from_slice(&ciphertext);
+
+        assert_eq!(decrypt("hunter2", &framed).unwrap(), b"payload");
+    }
+}
@@ -0,0 +1,1016 @@
+use anyhow::{anyhow, Result};
+use fast_qr::{
+    convert::{svg::SvgBuilder, Builder, Shape},
+    qr::QRBuilder,
+};
+use gloo::file::{callbacks::FileReader, File};
+use gloo::timers::callback::Interval;
+use image::ImageEncoder;
+use percent_encoding::utf8_percent_encode;
+use prost::Message;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DragEvent, Event, FileList, HtmlInputElement};
+use yew::prelude::*;
+
+use std::collections::{HashMap, HashSet};
+
+use proto::{MigrationPayload, OtpAlgorithm, OtpDigitCount, OtpParameters, OtpType};
+use worker::{DecodeOutput, DecodeRequest, DecodeResponse, DecodeWorker, ENCRYPTED_BACKUP_MAGIC};
+use yew_agent::{Spawnable, WorkerBridge};
+
+mod crypto;
+mod otp;
+mod proto;
+pub mod worker;
+
+/// Google Authenticator only fits this many accounts in a single migration QR.
+const EXPORT_BATCH_SIZE: usize = 10;
+
+/// Path to the `src/bin/worker.rs` bundle, as built and served by trunk
+/// alongside the main app.
+const WORKER_JS: &str = "/worker.js";
+
+pub enum Msg {
+    Files(Vec<File>),
+    Loaded(String, Vec<u8>),
+    Decoded(DecodeResponse),
+    ShowSvg(String),
+    Copied(String, CopyState),
+    Tick,
+    ToggleSelect(String),
+    Export,
+    PromptEncryptedExport,
+    PassphraseInput(String),
+    CancelPassphrasePrompt,
+    ConfirmEncryptedExport,
+    ConfirmDecrypt,
+}
+
+/// What the passphrase prompt is currently being used for.
+pub enum PassphrasePrompt {
+    Export,
+    Import { buffer: Vec<u8> },
+}
+
+pub struct EncryptedExport {
+    file_url: String,
+    qr_svgs: Vec<String>,
+}
+
+pub struct App {
+    readers: HashMap<String, FileReader>,
+    // Each in-flight file gets its own worker bridge, so a batch of dropped
+    // photos decodes in parallel instead of queueing on one shared worker.
+    // Holding the bridge here is also what keeps its worker alive until the
+    // response arrives.
+    decoding: HashMap<String, WorkerBridge<DecodeWorker>>,
+    output: Vec<Output>,
+    error: Option<String>,
+    selected: HashSet<String>,
+    export: Vec<String>,
+    passphrase_prompt: Option<PassphrasePrompt>,
+    passphrase_input: String,
+    encrypted_export: Option<EncryptedExport>,
+    _ticker: Interval,
+}
+
+pub struct Output {
+    issuer: String,
+    name: String,
+    secret: String,
+    secret_bytes: Vec<u8>,
+    kind: String,
+    otp_type: OtpType,
+    algorithm: Option<String>,
+    otp_algorithm: OtpAlgorithm,
+    digit_count: Option<String>,
+    otp_digits: OtpDigitCount,
+    counter: u64,
+    period: u64,
+    code: Option<String>,
+    seconds_remaining: Option<u64>,
+    url: String,
+    svg: String,
+    svg_markup: String,
+    show_svg: bool,
+    copied: Option<CopyState>,
+}
+
+#[derive(Copy, Clone)]
+pub enum CopyState {
+    Copied,
+    Failed,
+}
+
+impl Component for App {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let link = ctx.link().clone();
+        let ticker = Interval::new(1_000, move || link.send_message(Msg::Tick));
+
+        Self {
+            readers: HashMap::new(),
+            decoding: HashMap::new(),
+            output: Vec::new(),
+            error: None,
+            selected: HashSet::new(),
+            export: Vec::new(),
+            passphrase_prompt: None,
+            passphrase_input: String::new(),
+            encrypted_export: None,
+            _ticker: ticker,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Files(files) => {
+                self.readers.clear();
+                self.decoding.clear();
+                self.output.clear();
+                self.selected.clear();
+                self.export.clear();
+                self.passphrase_prompt = None;
+                self.passphrase_input.clear();
+                self.encrypted_export = None;
+
+                for file in files.into_iter() {
+                    let file_name = file.name();
+
+                    let task = {
+                        let link = ctx.link().clone();
+                        let file_name = file_name.clone();
+
+                        gloo::file::callbacks::read_as_bytes(&file, move |res| {
+                            link.send_message(Msg::Loaded(
+                                file_name,
+                                res.expect("failed to read file"),
+                            ))
+                        })
+                    };
+                    self.readers.insert(file_name, task);
+                }
+                true
+            }
+            Msg::Loaded(file_name, buffer) => {
+                if self.readers.remove(&file_name).is_none() {
+                    return false;
+                }
+
+                let link = ctx.link().clone();
+                let worker = DecodeWorker::spawner()
+                    .callback(move |response| link.send_message(Msg::Decoded(response)))
+                    .spawn(WORKER_JS);
+                worker.send(DecodeRequest {
+                    file_name: file_name.clone(),
+                    buffer,
+                });
+                self.decoding.insert(file_name, worker);
+                true
+            }
+            Msg::Decoded(response) => {
+                if self.decoding.remove(&response.file_name).is_none() {
+                    return false;
+                }
+
+                match response.result {
+                    Ok(DecodeOutput::Migration(message)) => {
+                        match MigrationPayload::decode(message.as_slice()) {
+                            Ok(migration) => self.apply_migration(migration),
+                            Err(err) => {
+                                self.output = vec![];
+                                self.error = Some(format!("Unknown error: {}", err));
+                            }
+                        }
+                    }
+                    Ok(DecodeOutput::EncryptedBackup(buffer)) => {
+                        self.passphrase_prompt = Some(PassphrasePrompt::Import { buffer });
+                        self.passphrase_input.clear();
+                        self.error = None;
+                    }
+                    Ok(DecodeOutput::NotFound) => {
+                        self.output = vec![];
+                        self.error = Some("No valid Google Authenticator Export QR code found in the uploaded image.".to_owned());
+                    }
+                    Err(err) => {
+                        self.output = vec![];
+                        self.error = Some(format!("Unknown error: {}", err));
+                    }
+                }
+                true
+            }
+            Msg::ShowSvg(url) => {
+                for output in self.output.iter_mut() {
+                    if output.url == url {
+                        output.show_svg = !output.show_svg;
+                    } else {
+                        output.show_svg = false;
+                    }
+                }
+                true
+            }
+            Msg::Copied(url, state) => {
+                for output in self.output.iter_mut() {
+                    if output.url == url {
+                        output.copied = Some(state);
+                    } else {
+                        output.copied = None;
+                    }
+                }
+                true
+            }
+            Msg::Tick => {
+                let unix_time = Self::unix_time();
+
+                for output in self.output.iter_mut() {
+                    output.code = otp::generate_code(
+                        &output.secret_bytes,
+                        output.otp_algorithm,
+                        output.otp_digits,
+                        output.otp_type,
+                        output.counter,
+                        unix_time,
+                        output.period,
+                    )
+                    .ok();
+
+                    output.seconds_remaining = match output.otp_type {
+                        OtpType::Totp | OtpType::Unspecified => {
+                            Some(otp::seconds_remaining(unix_time, output.period))
+                        }
+                        OtpType::Hotp => None,
+                    };
+                }
+                true
+            }
+            Msg::ToggleSelect(url) => {
+                if !self.selected.remove(&url) {
+                    self.selected.insert(url);
+                }
+                true
+            }
+            Msg::Export => {
+                let selected: Vec<&Output> = self
+                    .output
+                    .iter()
+                    .filter(|output| self.selected.contains(&output.url))
+                    .collect();
+
+                match Self::build_export(&selected) {
+                    Ok(export) => {
+                        self.export = export;
+                        self.error = None;
+                    }
+                    Err(err) => {
+                        self.export = Vec::new();
+                        self.error = Some(format!("Could not build export QR codes: {}", err));
+                    }
+                }
+                true
+            }
+            Msg::PromptEncryptedExport => {
+                self.passphrase_prompt = Some(PassphrasePrompt::Export);
+                self.passphrase_input.clear();
+                true
+            }
+            Msg::PassphraseInput(input) => {
+                self.passphrase_input = input;
+                true
+            }
+            Msg::CancelPassphrasePrompt => {
+                self.passphrase_prompt = None;
+                self.passphrase_input.clear();
+                true
+            }
+            Msg::ConfirmEncryptedExport => {
+                let selected: Vec<&Output> = self
+                    .output
+                    .iter()
+                    .filter(|output| self.selected.contains(&output.url))
+                    .collect();
+
+                match Self::build_encrypted_export(&self.passphrase_input, &selected) {
+                    Ok(export) => {
+                        self.encrypted_export = Some(export);
+                        self.error = None;
+                    }
+                    Err(err) => {
+                        self.encrypted_export = None;
+                        self.error = Some(format!("Could not build encrypted backup: {}", err));
+                    }
+                }
+                self.passphrase_prompt = None;
+                self.passphrase_input.clear();
+                true
+            }
+            Msg::ConfirmDecrypt => {
+                if let Some(PassphrasePrompt::Import { buffer }) = self.passphrase_prompt.take() {
+                    match crypto::decrypt(&self.passphrase_input, &buffer)
+                        .and_then(|message| Ok(MigrationPayload::decode(message.as_slice())?))
+                    {
+                        Ok(migration) => self.apply_migration(migration),
+                        Err(err) => {
+                            self.error = Some(format!("Could not decrypt backup: {}", err));
+                        }
+                    }
+                }
+                self.passphrase_input.clear();
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div>
+                <label class="upload-wrapper" for="file-upload">
+                    <div
+                        class="upload"
+                        ondrop={ctx.link().callback(|event: DragEvent| {
+                            event.prevent_default();
+                            let files = event.data_transfer().unwrap().files();
+                            Self::collect_files(files)
+                        })}
+                        ondragover={Callback::from(|event: DragEvent| {
+                            event.prevent_default();
+                        })}
+                        ondragenter={Callback::from(|event: DragEvent| {
+                            event.prevent_default();
+                        })}
+                    >
+                        <p>{"Drop your images here or click to select"}</p>
+                    </div>
+                </label>
+                <input
+                    id="file-upload"
+                    type="file"
+                    multiple=true
+                    accept="image/jpeg,image/png,.bin,application/octet-stream"
+                    onchange={ctx.link().callback(move |e: Event| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        Self::collect_files(input.files())
+                    })}
+                />
+                if let Some(ref error) = self.error {
+                    <p>{error}</p>
+                }
+                if !self.readers.is_empty() || !self.decoding.is_empty() {
+                    <ul class="decoding">
+                        { for self.readers.keys().map(|file_name| html! {
+                            <li key={file_name.clone()}>{format!("{}: reading...", file_name)}</li>
+                        }) }
+                        { for self.decoding.keys().map(|file_name| html! {
+                            <li key={file_name.clone()}>{format!("{}: decoding...", file_name)}</li>
+                        }) }
+                    </ul>
+                }
+                if !self.output.is_empty() {
+                    <div class="output">
+                        { for self.output.iter().map(|o| Self::view_output(ctx, o, self.selected.contains(&o.url))) }
+                    </div>
+                    <p>
+                        <button
+                            class="export__button"
+                            onclick={{
+                                let outputs: Vec<(String, String)> = self
+                                    .output
+                                    .iter()
+                                    .map(|o| (o.svg_markup.clone(), Self::download_file_name(o)))
+                                    .collect();
+
+                                Callback::from(move |_| {
+                                    for (svg_markup, file_name) in &outputs {
+                                        Self::trigger_download(
+                                            svg_markup.as_bytes(),
+                                            "image/svg+xml",
+                                            &format!("{file_name}.svg"),
+                                        );
+                                    }
+                                })
+                            }}
+                        >
+                            {"Download all (SVG)"}
+                        </button>
+                        {" "}
+                        <button
+                            class="export__button"
+                            disabled={self.selected.is_empty()}
+                            onclick={ctx.link().callback(|_| Msg::Export)}
+                        >
+                            {"Export selected as migration QR"}
+                        </button>
+                        {" "}
+                        <button
+                            class="export__button"
+                            disabled={self.selected.is_empty()}
+                            onclick={ctx.link().callback(|_| Msg::PromptEncryptedExport)}
+                        >
+                            {"Export selected as encrypted backup"}
+                        </button>
+                    </p>
+                }
+                if !self.export.is_empty() {
+                    <div class="export">
+                        { for self.export.iter().enumerate().map(|(i, svg)| html! {
+                            <img
+                                class="export__qr-code"
+                                key={i}
+                                src={svg.clone()}
+                                alt="Migration export QR code" />
+                        }) }
+                    </div>
+                }
+                if let Some(ref export) = self.encrypted_export {
+                    <div class="export export--encrypted">
+                        { for export.qr_svgs.iter().enumerate().map(|(i, svg)| html! {
+                            <img
+                                class="export__qr-code"
+                                key={i}
+                                src={svg.clone()}
+                                alt="Encrypted backup QR code" />
+                        }) }
+                        <p>
+                            <a
+                                class="export__download"
+                                href={export.file_url.clone()}
+                                download="exodus-backup.bin"
+                            >
+                                {"Download encrypted backup (.bin)"}
+                            </a>
+                        </p>
+                    </div>
+                }
+                if let Some(ref prompt) = self.passphrase_prompt {
+                    { Self::view_passphrase_prompt(ctx, prompt, &self.passphrase_input) }
+                }
+            </div>
+        }
+    }
+}
+
+impl App {
+    fn unix_time() -> u64 {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+
+    fn collect_files(files: Option<FileList>) -> Msg {
+        if let Some(files) = files {
+            let files = js_sys::try_iter(&files)
+                .unwrap()
+                .unwrap()
+                .map(|v| web_sys::File::from(v.unwrap()))
+                .map(File::from)
+                .collect();
+
+            Msg::Files(files)
+        } else {
+            Msg::Files(vec![])
+        }
+    }
+
+    fn view_passphrase_prompt(ctx: &Context<Self>, prompt: &PassphrasePrompt, input: &str) -> Html {
+        let title = match prompt {
+            PassphrasePrompt::Export => "Set a passphrase for the backup",
+            PassphrasePrompt::Import { .. } => "Enter the backup passphrase",
+        };
+
+        let confirm_button = match prompt {
+            PassphrasePrompt::Export => html! {
+                <button onclick={ctx.link().callback(|_| Msg::ConfirmEncryptedExport)}>
+                    {"Confirm"}
+                </button>
+            },
+            PassphrasePrompt::Import { .. } => html! {
+                <button onclick={ctx.link().callback(|_| Msg::ConfirmDecrypt)}>
+                    {"Confirm"}
+                </button>
+            },
+        };
+
+        html! {
+            <div class="passphrase-prompt">
+                <p>{title}</p>
+                <input
+                    type="password"
+                    value={input.to_owned()}
+                    oninput={ctx.link().callback(|e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        Msg::PassphraseInput(input.value())
+                    })}
+                />
+                <button onclick={ctx.link().callback(|_| Msg::CancelPassphrasePrompt)}>
+                    {"Cancel"}
+                </button>
+                {" "}
+                {confirm_button}
+            </div>
+        }
+    }
+
+    fn view_output(ctx: &Context<Self>, output: &Output, selected: bool) -> Html {
+        let url = output.url.clone();
+        let show_qr_code = ctx.link().callback(move |_| Msg::ShowSvg(url.clone()));
+
+        let url = output.url.clone();
+        let toggle_select = ctx.link().callback(move |_| Msg::ToggleSelect(url.clone()));
+
+        let svg_markup = output.svg_markup.clone();
+        let file_name = Self::download_file_name(output);
+        let download_svg = Callback::from(move |_| {
+            Self::trigger_download(svg_markup.as_bytes(), "image/svg+xml", &format!("{file_name}.svg"));
+        });
+
+        let url = output.url.clone();
+        let file_name = Self::download_file_name(output);
+        let download_png = Callback::from(move |_| {
+            if let Ok(png) = Self::build_qr_png(&url) {
+                Self::trigger_download(&png, "image/png", &format!("{file_name}.png"));
+            }
+        });
+
+        let url = output.url.clone();
+        let copy_as_text = ctx.link().callback_future(move |_| {
+            let url = url.clone();
+            async move {
+                let navigator = web_sys::window().unwrap().navigator();
+
+                let Some(clipboard) = navigator.clipboard() else {
+                    return Msg::Copied(url.clone(), CopyState::Failed);
+                };
+
+                let copy_result: JsFuture = clipboard.write_text(&url).into();
+
+                let state = match copy_result.await {
+                    Ok(_) => CopyState::Copied,
+                    Err(_) => CopyState::Failed,
+                };
+
+                Msg::Copied(url.clone(), state)
+            }
+        });
+
+        html! {
+            <div class="otp">
+                <label class="otp__select">
+                    <input type="checkbox" checked={selected} onchange={toggle_select} />
+                    {" Select for export"}
+                </label>
+                <h2 class="otp__name">{&output.issuer} {" "} {&output.name}</h2>
+                if let Some(ref code) = output.code {
+                    <p class="otp__code">
+                        {code}
+                        if let Some(seconds_remaining) = output.seconds_remaining {
+                            {" "}
+                            <span class="otp__countdown">{format!("({}s)", seconds_remaining)}</span>
+                        }
+                    </p>
+                }
+                <p>
+                    <button class="otp__show-qr-code" onclick={show_qr_code.clone()}>
+                        {"Show QR code"}
+                    </button>
+                    {" "}
+                    <button class="otp__copy" onclick={copy_as_text}>
+                        {"Copy as text"}
+                    </button>
+                    {" "}
+                    <button class="otp__download" onclick={download_svg}>
+                        {"Download SVG"}
+                    </button>
+                    {" "}
+                    <button class="otp__download" onclick={download_png}>
+                        {"Download PNG"}
+                    </button>
+                    if let Some(CopyState::Copied) = output.copied {
+                        {" "}
+                        <span class="otp__copied">{"Copied!"}</span>
+                    } else if let Some(CopyState::Failed) = output.copied {
+                        {" "}
+                        <span class="otp__copied">{"Could not copy, use the URL in details."}</span>
+                    }
+                </p>
+                <details>
+                    <summary>{"Details"}</summary>
+                    <dl>
+                        if !output.issuer.is_empty() {
+                            <dt>{"Issuer"}</dt>
+                            <dd>{&output.issuer}</dd>
+                        }
+                        <dt>{"Name"}</dt>
+                        <dd>{&output.name}</dd>
+                        <dt>{"Type"}</dt>
+                        <dd>{&output.kind}</dd>
+                        if let Some(ref algorithm) = output.algorithm {
+                            <dt>{"Algorithm"}</dt>
+                            <dd>{algorithm}</dd>
+                        }
+                        if let Some(ref digit_count) = output.digit_count {
+                            <dt>{"Digits"}</dt>
+                            <dd>{digit_count}</dd>
+                        }
+                        <dt>{"Secret"}</dt>
+                        <dd>{&output.secret}</dd>
+                        <dt>{"URL"}</dt>
+                        <dd>{&output.url}</dd>
+                    </dl>
+                </details>
+                <div class="otp__qr-code_wrapper">
+                    <img
+                        class={classes!(
+                            "otp__qr-code",
+                            if output.show_svg {
+                                Some("otp__qr-code--show")
+                            } else {
+                                None
+                            }
+                        )}
+                        onclick={show_qr_code}
+                        src={output.svg.clone()}
+                        alt="One time pad QR code" />
+                </div>
+            </div>
+        }
+    }
+
+    fn apply_migration(&mut self, migration: MigrationPayload) {
+        let mut errors = vec![];
+
+        for params in migration.otp_parameters {
+            match Self::migration_to_output(params) {
+                Ok(output) => self.output.push(output),
+                Err(err) => errors.push(format!("{}", err)),
+            }
+        }
+
+        match &errors[..] {
+            &[] => self.error = None,
+            &[ref error] => {
+                self.error = Some(format!("One account could not be read: {}", error))
+            }
+            errors => {
+                self.error = Some(format!(
+                    "{} accounts could not be read: {}",
+                    errors.len(),
+                    errors.join(", ")
+                ))
+            }
+        }
+    }
+
+    fn migration_to_output(params: OtpParameters) -> Result<Output> {
+        let secret_bytes = params.secret.clone();
+        let secret = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &params.secret);
+
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.append_pair("secret", secret.as_str());
+
+        let kind = match params.type_() {
+            OtpType::Unspecified => return Err(anyhow!("unknown otp type")),
+            OtpType::Hotp => {
+                query.append_pair("counter", &params.counter.to_string());
+                "hotp"
+            }
+            OtpType::Totp => "totp",
+        };
+
+        let name = if !params.issuer.is_empty() {
+            query.append_pair("issuer", &params.issuer);
+
+            utf8_percent_encode(
+                &format!("{}:{}", params.issuer, params.name),
+                percent_encoding::NON_ALPHANUMERIC,
+            )
+            .to_string()
+        } else {
+            utf8_percent_encode(&params.name, percent_encoding::NON_ALPHANUMERIC).to_string()
+        };
+
+        let algorithm = match params.algorithm() {
+            OtpAlgorithm::Unspecified => None,
+            OtpAlgorithm::Sha1 => Some("SHA1"),
+            OtpAlgorithm::Sha256 => Some("SHA256"),
+            OtpAlgorithm::Sha512 => Some("SHA512"),
+            OtpAlgorithm::Md5 => Some("MD5"),
+        };
+
+        if let Some(algorithm) = algorithm {
+            query.append_pair("algorithm", algorithm);
+        }
+
+        let digit_count = match params.digits() {
+            OtpDigitCount::Unspecified => None,
+            OtpDigitCount::Six => Some("6"),
+            OtpDigitCount::Eight => Some("8"),
+        };
+
+        if let Some(digit_count) = digit_count {
+            query.append_pair("digits", digit_count);
+        }
+
+        // 0 means "unspecified" (Google's own export format has no period
+        // field at all), so only surface it in the URL when it was actually
+        // set to something other than the default.
+        let period = if params.period > 0 {
+            params.period as u64
+        } else {
+            otp::DEFAULT_PERIOD
+        };
+
+        if period != otp::DEFAULT_PERIOD {
+            query.append_pair("period", &period.to_string());
+        }
+
+        let querystring = query.finish();
+
+        // https://github.com/google/google-authenticator/wiki/Key-Uri-Format
+        let url = format!("otpauth://{kind}/{name}?{}", querystring);
+
+        let svg_markup = Self::render_qr_svg(&url);
+        let svg = Self::svg_to_data_uri(&svg_markup);
+
+        let otp_type = params.type_();
+        let otp_algorithm = params.algorithm();
+        let otp_digits = params.digits();
+        let counter = params.counter as u64;
+
+        let unix_time = Self::unix_time();
+        let code = otp::generate_code(
+            &secret_bytes,
+            otp_algorithm,
+            otp_digits,
+            otp_type,
+            counter,
+            unix_time,
+            period,
+        )
+        .ok();
+
+        let seconds_remaining = match otp_type {
+            OtpType::Totp | OtpType::Unspecified => {
+                Some(otp::seconds_remaining(unix_time, period))
+            }
+            OtpType::Hotp => None,
+        };
+
+        Ok(Output {
+            issuer: params.issuer,
+            name: params.name,
+            kind: kind.to_uppercase(),
+            otp_type,
+            algorithm: algorithm.map(Into::into),
+            otp_algorithm,
+            digit_count: digit_count.map(Into::into),
+            otp_digits,
+            counter,
+            period,
+            code,
+            seconds_remaining,
+            secret,
+            secret_bytes,
+            url,
+            svg,
+            svg_markup,
+            show_svg: false,
+            copied: None,
+        })
+    }
+
+    fn render_qr_svg(url: &str) -> String {
+        let qrcode = QRBuilder::new(url).ecl(fast_qr::ECL::L).build().unwrap();
+
+        SvgBuilder::default().shape(Shape::Square).to_str(&qrcode)
+    }
+
+    fn svg_to_data_uri(svg: &str) -> String {
+        format!(
+            "data:image/svg+xml,{}",
+            percent_encoding::utf8_percent_encode(svg, percent_encoding::NON_ALPHANUMERIC)
+        )
+    }
+
+    fn build_qr_svg(url: &str) -> String {
+        Self::svg_to_data_uri(&Self::render_qr_svg(url))
+    }
+
+    /// Rasterizes the QR code for `url` into a PNG, for users who want a
+    /// raster image instead of the SVG (e.g. to paste into a document).
+    fn build_qr_png(url: &str) -> Result<Vec<u8>> {
+        const MODULE_PX: u32 = 8;
+        const QUIET_ZONE_MODULES: u32 = 4;
+
+        let qrcode = QRBuilder::new(url).ecl(fast_qr::ECL::L).build().unwrap();
+        let size = qrcode.size as u32;
+        let image_size = (size + QUIET_ZONE_MODULES * 2) * MODULE_PX;
+
+        let mut image =
+            image::RgbaImage::from_pixel(image_size, image_size, image::Rgba([255, 255, 255, 255]));
+
+        for y in 0..size {
+            for x in 0..size {
+                if qrcode.data[(y * size + x) as usize].value() {
+                    let px = (x + QUIET_ZONE_MODULES) * MODULE_PX;
+                    let py = (y + QUIET_ZONE_MODULES) * MODULE_PX;
+
+                    for dy in 0..MODULE_PX {
+                        for dx in 0..MODULE_PX {
+                            image.put_pixel(px + dx, py + dy, image::Rgba([0, 0, 0, 255]));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png).write_image(
+            image.as_raw(),
+            image_size,
+            image_size,
+            image::ColorType::Rgba8,
+        )?;
+
+        Ok(png)
+    }
+
+    fn download_file_name(output: &Output) -> String {
+        let name = if !output.issuer.is_empty() {
+            format!("{}-{}", output.issuer, output.name)
+        } else {
+            output.name.clone()
+        };
+
+        name.chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+            .collect()
+    }
+
+    fn trigger_download(data: &[u8], mime: &str, file_name: &str) {
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data).into());
+        let mut options = web_sys::BlobPropertyBag::new();
+        options.type_(mime);
+
+        let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).unwrap();
+        let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
+
+        let anchor = gloo::utils::document()
+            .create_element("a")
+            .unwrap()
+            .dyn_into::<web_sys::HtmlAnchorElement>()
+            .unwrap();
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+
+        web_sys::Url::revoke_object_url(&url).ok();
+    }
+
+    fn output_to_otp_parameters(output: &Output) -> OtpParameters {
+        OtpParameters {
+            secret: output.secret_bytes.clone(),
+            name: output.name.clone(),
+            issuer: output.issuer.clone(),
+            algorithm: output.otp_algorithm as i32,
+            digits: output.otp_digits as i32,
+            type_: output.otp_type as i32,
+            counter: output.counter as i64,
+            // 0 means "default" (see `migration_to_output`), so a re-export
+            // of an account that never had an explicit period round-trips
+            // back to the same "unspecified" state instead of hardcoding 30.
+            period: if output.period == otp::DEFAULT_PERIOD {
+                0
+            } else {
+                output.period as i64
+            },
+        }
+    }
+
+    /// Google Authenticator rejects a migration QR with a random batch id of
+    /// zero, so reroll until we get a non-zero one.
+    fn random_batch_id() -> i32 {
+        loop {
+            let batch_id = (js_sys::Math::random() * i32::MAX as f64) as i32;
+            if batch_id != 0 {
+                return batch_id;
+            }
+        }
+    }
+
+    fn build_export(outputs: &[&Output]) -> Result<Vec<String>> {
+        let batch_id = Self::random_batch_id();
+
+        let batches: Vec<Vec<OtpParameters>> = outputs
+            .chunks(EXPORT_BATCH_SIZE)
+            .map(|chunk| chunk.iter().map(|output| Self::output_to_otp_parameters(output)).collect())
+            .collect();
+
+        let batch_size = batches.len() as i32;
+
+        batches
+            .into_iter()
+            .enumerate()
+            .map(|(batch_index, otp_parameters)| {
+                let payload = MigrationPayload {
+                    otp_parameters,
+                    version: 1,
+                    batch_size,
+                    batch_index: batch_index as i32,
+                    batch_id,
+                };
+
+                let message = payload.encode_to_vec();
+                let data = base64::encode(message);
+
+                let url = format!(
+                    "otpauth-migration://offline?data={}",
+                    utf8_percent_encode(&data, percent_encoding::NON_ALPHANUMERIC)
+                );
+
+                Ok(Self::build_qr_svg(&url))
+            })
+            .collect()
+    }
+
+    fn build_encrypted_export(passphrase: &str, outputs: &[&Output]) -> Result<EncryptedExport> {
+        if passphrase.is_empty() {
+            return Err(anyhow!("passphrase must not be empty"));
+        }
+
+        // The downloadable file isn't scanned by a camera, so it can carry
+        // every selected account in one encrypted blob regardless of count.
+        let otp_parameters: Vec<OtpParameters> = outputs
+            .iter()
+            .map(|output| Self::output_to_otp_parameters(output))
+            .collect();
+
+        let payload = MigrationPayload {
+            otp_parameters,
+            version: 1,
+            batch_size: 1,
+            batch_index: 0,
+            batch_id: 0,
+        };
+
+        let framed = crypto::encrypt(passphrase, &payload.encode_to_vec())?;
+
+        let mut file_bytes = ENCRYPTED_BACKUP_MAGIC.to_vec();
+        file_bytes.extend_from_slice(&framed);
+
+        let file_url = format!(
+            "data:application/octet-stream;base64,{}",
+            base64::encode(&file_bytes)
+        );
+
+        // The QR code is capacity-limited the same way a plain migration QR
+        // is, so chunk it the same way `build_export` does, encrypting each
+        // batch separately. Scanning each QR in turn decrypts and appends
+        // its batch just like a normal multi-part migration import does.
+        let batch_id = Self::random_batch_id();
+
+        let batches: Vec<Vec<OtpParameters>> = outputs
+            .chunks(EXPORT_BATCH_SIZE)
+            .map(|chunk| chunk.iter().map(|output| Self::output_to_otp_parameters(output)).collect())
+            .collect();
+
+        let batch_size = batches.len() as i32;
+
+        let qr_svgs = batches
+            .into_iter()
+            .enumerate()
+            .map(|(batch_index, otp_parameters)| {
+                let payload = MigrationPayload {
+                    otp_parameters,
+                    version: 1,
+                    batch_size,
+                    batch_index: batch_index as i32,
+                    batch_id,
+                };
+
+                let framed = crypto::encrypt(passphrase, &payload.encode_to_vec())?;
+
+                let qr_data = base64::encode_config(&framed, base64::URL_SAFE_NO_PAD);
+                let qr_url = format!(
+                    "exodus-encrypted://offline?data={}",
+                    utf8_percent_encode(&qr_data, percent_encoding::NON_ALPHANUMERIC)
+                );
+
+                Ok(Self::build_qr_svg(&qr_url))
+            })
+            .collect::<Result<Vec<String>>>()?;
+
+        Ok(EncryptedExport {
+            file_url,
+            qr_svgs,
+        })
+    }
+}
+
+/// Mounts the app onto `#app`. Called from `src/main.rs`.
+pub fn run() {
+    let document = gloo::utils::document();
+
+    let app = document.get_element_by_id("app").unwrap();
+
+    yew::Renderer::<App>::with_root(app).render();
+}
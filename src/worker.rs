@@ -0,0 +1,231 @@
+use anyhow::{anyhow, Error, Result};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use yew_agent::{HandlerId, Worker, WorkerScope};
+
+use crate::proto::{MigrationPayload, OtpAlgorithm, OtpDigitCount, OtpParameters, OtpType};
+
+/// Prefix on an uploaded file that marks it as an exodus encrypted backup
+/// rather than an image to run through the QR reader. Written by
+/// `App::build_encrypted_export` and read back here.
+pub(crate) const ENCRYPTED_BACKUP_MAGIC: &[u8] = b"exodus-encrypted-backup\0";
+
+#[derive(Serialize, Deserialize)]
+pub struct DecodeRequest {
+    pub file_name: String,
+    pub buffer: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DecodeResponse {
+    pub file_name: String,
+    pub result: Result<DecodeOutput, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum DecodeOutput {
+    /// An encoded `MigrationPayload`, re-encoded here since prost messages
+    /// aren't `serde`-friendly enough to cross the worker boundary directly.
+    Migration(Vec<u8>),
+    /// A still-encrypted backup, awaiting a passphrase on the main thread.
+    EncryptedBackup(Vec<u8>),
+    NotFound,
+}
+
+/// Runs the `image` + `rqrr` + proto decoding of an uploaded file off the
+/// main thread, so dropping several large photos doesn't freeze the page.
+pub struct DecodeWorker;
+
+impl Worker for DecodeWorker {
+    type Message = ();
+    type Input = DecodeRequest;
+    type Output = DecodeResponse;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        DecodeWorker
+    }
+
+    fn update(&mut self, _scope: &WorkerScope<Self>, _msg: Self::Message) {}
+
+    fn received(&mut self, scope: &WorkerScope<Self>, request: Self::Input, id: HandlerId) {
+        let result = decode_file(request.buffer)
+            .map(|decoded| match decoded {
+                Some(DecodedFile::Migration(migration)) => {
+                    DecodeOutput::Migration(migration.encode_to_vec())
+                }
+                Some(DecodedFile::EncryptedBackup(buffer)) => {
+                    DecodeOutput::EncryptedBackup(buffer)
+                }
+                None => DecodeOutput::NotFound,
+            })
+            .map_err(|err| format!("{}", err));
+
+        scope.respond(
+            id,
+            DecodeResponse {
+                file_name: request.file_name,
+                result,
+            },
+        );
+    }
+}
+
+/// What a decoded QR code or uploaded file turned out to contain.
+enum DecodedFile {
+    Migration(MigrationPayload),
+    EncryptedBackup(Vec<u8>),
+}
+
+fn migration_from_url(url: &url::Url) -> Result<MigrationPayload> {
+    let (_, data) = url
+        .query_pairs()
+        .find(|(key, _)| key == "data")
+        .ok_or_else(|| anyhow!("could not find data param in url"))?;
+
+    let message = base64::decode(data.as_ref())?;
+
+    Ok(MigrationPayload::decode(message.as_slice())?)
+}
+
+/// Parses a plain `otpauth://totp/...` or `otpauth://hotp/...` URL, as
+/// produced by most non-Google authenticator apps, into a single-account
+/// `MigrationPayload` so it can flow through the same pipeline as a
+/// Google Authenticator export.
+fn otpauth_url_to_migration(url: &url::Url) -> Result<MigrationPayload> {
+    let otp_type = match url.host_str() {
+        Some("totp") => OtpType::Totp,
+        Some("hotp") => OtpType::Hotp,
+        host => return Err(anyhow!("unsupported otpauth type: {:?}", host)),
+    };
+
+    let label = percent_encoding::percent_decode_str(url.path().trim_start_matches('/'))
+        .decode_utf8()?
+        .into_owned();
+
+    let (label_issuer, name) = match label.split_once(':') {
+        Some((issuer, name)) => (issuer.trim().to_owned(), name.trim().to_owned()),
+        None => (String::new(), label),
+    };
+
+    let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+    let issuer = params.get("issuer").cloned().unwrap_or(label_issuer);
+
+    let secret = params
+        .get("secret")
+        .ok_or_else(|| anyhow!("missing secret parameter"))?;
+    let secret = base32::decode(base32::Alphabet::RFC4648 { padding: false }, secret)
+        .ok_or_else(|| anyhow!("invalid base32 secret"))?;
+
+    let algorithm = match params.get("algorithm").map(String::as_str) {
+        Some("SHA256") => OtpAlgorithm::Sha256,
+        Some("SHA512") => OtpAlgorithm::Sha512,
+        Some("MD5") => OtpAlgorithm::Md5,
+        _ => OtpAlgorithm::Sha1,
+    };
+
+    let digits = match params.get("digits").map(String::as_str) {
+        Some("8") => OtpDigitCount::Eight,
+        _ => OtpDigitCount::Six,
+    };
+
+    let counter = params
+        .get("counter")
+        .and_then(|counter| counter.parse().ok())
+        .unwrap_or(0);
+
+    // 0 means "use the default period" once this flows through
+    // `migration_to_output`; the field is an app extension, since Google's
+    // migration export format has no per-account period at all.
+    let period = params
+        .get("period")
+        .and_then(|period| period.parse().ok())
+        .unwrap_or(0);
+
+    Ok(MigrationPayload {
+        otp_parameters: vec![OtpParameters {
+            secret,
+            name,
+            issuer,
+            algorithm: algorithm as i32,
+            digits: digits as i32,
+            type_: otp_type as i32,
+            counter,
+            period,
+        }],
+        version: 1,
+        batch_size: 1,
+        batch_index: 0,
+        batch_id: 0,
+    })
+}
+
+/// Parses an `exodus-encrypted://offline?data=...` QR code into the raw
+/// `params || salt || nonce || ciphertext` frame, still awaiting a passphrase.
+fn encrypted_backup_from_url(url: &url::Url) -> Result<Vec<u8>> {
+    let (_, data) = url
+        .query_pairs()
+        .find(|(key, _)| key == "data")
+        .ok_or_else(|| anyhow!("could not find data param in url"))?;
+
+    Ok(base64::decode_config(
+        data.as_ref(),
+        base64::URL_SAFE_NO_PAD,
+    )?)
+}
+
+fn decode_file(buffer: Vec<u8>) -> Result<Option<DecodedFile>> {
+    if let Some(backup) = buffer.strip_prefix(ENCRYPTED_BACKUP_MAGIC) {
+        return Ok(Some(DecodedFile::EncryptedBackup(backup.to_vec())));
+    }
+
+    fn extract(buffer: &[u8], threshold: bool) -> Result<Option<DecodedFile>> {
+        let mut img = image::load_from_memory(buffer)?.to_luma8();
+
+        if threshold {
+            for pixel in img.pixels_mut() {
+                if pixel[0] > 128 {
+                    pixel[0] = 255;
+                } else {
+                    pixel[0] = 0;
+                }
+            }
+        }
+
+        let mut img = rqrr::PreparedImage::prepare(img);
+
+        let grids = img.detect_grids();
+
+        let decoded = grids
+            .into_iter()
+            .flat_map(|grid| {
+                let (_meta, content) = grid.decode()?;
+
+                let url = url::Url::parse(&content)?;
+
+                Ok::<_, Error>(url)
+            })
+            .flat_map(|url| match url.scheme() {
+                "otpauth-migration" => migration_from_url(&url).map(DecodedFile::Migration),
+                "otpauth" => otpauth_url_to_migration(&url).map(DecodedFile::Migration),
+                "exodus-encrypted" => {
+                    encrypted_backup_from_url(&url).map(DecodedFile::EncryptedBackup)
+                }
+                scheme => Err(anyhow!("unsupported QR code scheme: {}", scheme)),
+            })
+            .next();
+
+        Ok(decoded)
+    }
+
+    let decoded = extract(&buffer, false)?;
+
+    if decoded.is_some() {
+        Ok(decoded)
+    } else {
+        // If we fail to parse the image, try to run a basic threshold filter
+        // to counteract any JPEG compression artefacts.
+        extract(&buffer, true)
+    }
+}